@@ -1,39 +1,41 @@
-use anyhow::{Context, Error};
+use anyhow::Context;
 use serde_derive::Deserialize;
 
-use backedup::{Config, SlotConfig};
-
-#[derive(Deserialize, Debug)]
-struct SlotConfiguration {
-    yearly: usize,
-    monthly: usize,
-    daily: usize,
+#[derive(Deserialize, Debug, Default)]
+pub(super) struct SlotConfiguration {
+    #[serde(default)]
+    pub(super) yearly: usize,
+    #[serde(default)]
+    pub(super) monthly: usize,
+    #[serde(default)]
+    pub(super) weekly: usize,
+    #[serde(default)]
+    pub(super) daily: usize,
     #[serde(default)]
-    hourly: usize,
+    pub(super) hourly: usize,
     #[serde(default)]
-    minutely: usize,
+    pub(super) minutely: usize,
 }
 
-#[derive(Deserialize, Debug)]
-struct ConfigFile {
-    slots: SlotConfiguration,
+#[derive(Deserialize, Debug, Default)]
+pub(super) struct ConfigFile {
+    #[serde(default)]
+    pub(super) slots: SlotConfiguration,
+    #[serde(default)]
+    pub(super) pattern: Vec<String>,
+    #[serde(default)]
+    pub(super) protect: Vec<String>,
+    pub(super) regex: Option<String>,
+    #[serde(default)]
+    pub(super) use_mtime: bool,
     #[serde(default)]
-    pattern: Vec<String>,
-    regex: Option<String>,
+    pub(super) recursive: bool,
 }
 
-pub fn from(path: &str) -> anyhow::Result<Config> {
+/// Load and parse a [ConfigFile] layer used as the base for [`super::ArgParser::to_plan`]'s merge.
+pub(super) fn load(path: &str) -> anyhow::Result<ConfigFile> {
     let data = std::fs::read_to_string(path).context("Can't read config from file")?;
-    let config: ConfigFile = toml::from_str(&data).context("Problem parsing config")?;
-    let slots = &config.slots;
-    let slot_config = SlotConfig::new(
-        slots.yearly,
-        slots.monthly,
-        slots.daily,
-        slots.hourly,
-        slots.minutely,
-    )?;
-    Config::new(slot_config, &config.pattern, config.regex.as_deref()).map_err(Error::new)
+    toml::from_str(&data).context("Problem parsing config")
 }
 
 #[cfg(test)]
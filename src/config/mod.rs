@@ -1,9 +1,7 @@
 use anyhow::Error;
 use clap::Parser;
 
-use backedup::{Config, Plan, SlotConfig};
-
-use crate::config;
+use backedup::{Config, FilesystemBackend, Plan, SlotConfig};
 
 mod file;
 
@@ -17,38 +15,55 @@ pub struct ArgParser {
     config: Option<String>,
 
     ///wildcard filename pattern to look for, quote it to prevent shell expansion.
-    /// Can be provided several times
+    /// Can be provided several times. Appended to any patterns from --config.
     #[clap(short, long)]
     pattern: Vec<String>,
 
-    ///set number of backups for yearly slot
-    #[clap(default_value_t = 0, short, long)]
-    yearly: usize,
+    ///wildcard filename pattern for entries that are always kept, regardless of slots.
+    /// Can be provided several times. Appended to any protect patterns from --config.
+    #[clap(short('P'), long)]
+    protect: Vec<String>,
+
+    ///set number of backups for yearly slot. Overrides --config if both are given
+    #[clap(short, long)]
+    yearly: Option<usize>,
+
+    ///set number of backups for monthly slot. Overrides --config if both are given
+    #[clap(short, long)]
+    monthly: Option<usize>,
 
-    ///set number of backups for monthly slot
-    #[clap(default_value_t = 0, short, long)]
-    monthly: usize,
+    ///set number of backups for weekly slot. Overrides --config if both are given
+    #[clap(short, long)]
+    weekly: Option<usize>,
 
-    ///set number of backups for daily slot
-    #[clap(default_value_t = 0, short, long)]
-    daily: usize,
+    ///set number of backups for daily slot. Overrides --config if both are given
+    #[clap(short, long)]
+    daily: Option<usize>,
 
-    ///set number of backups for hourly slot
-    #[clap(default_value_t = 0, short, long)]
-    hourly: usize,
+    ///set number of backups for hourly slot. Overrides --config if both are given
+    #[clap(short, long)]
+    hourly: Option<usize>,
 
-    ///set number of backups for minutely slot
-    #[clap(default_value_t = 0, short('M'), long)]
-    minutely: usize,
+    ///set number of backups for minutely slot. Overrides --config if both are given
+    #[clap(short('M'), long)]
+    minutely: Option<usize>,
 
     ///provide alternate regex for parsing timeslots. At least year, month and day must be provided and named
-    /// eg '(?P<year>\d{{2}})(?P<month>\d{{2}})(?P<day>\d{{2}})'
+    /// eg '(?P<year>\d{{2}})(?P<month>\d{{2}})(?P<day>\d{{2}})'. Overrides --config if both are given
     #[clap(short, long)]
     regex: Option<String>,
 
     ///execute plan and remove timestamped files not matching a slot
     #[clap(short, long)]
     pub(crate) execute: bool,
+
+    ///fall back to file modification time when the filename carries no timestamp
+    #[clap(short, long)]
+    use_mtime: bool,
+
+    ///walk subdirectories to discover directory-style snapshots and nested backup sets
+    #[clap(long)]
+    recursive: bool,
 }
 
 impl ArgParser {
@@ -56,21 +71,76 @@ impl ArgParser {
         ArgParser::parse()
     }
 
+    /// Builds the effective [Config] by layering `--config` as the base and letting any
+    /// explicitly-provided CLI flag override its fields, so a shared TOML policy can be
+    /// tweaked ad hoc without duplicating the whole file.
     pub fn to_plan(&self) -> anyhow::Result<Plan> {
-        let config = match &self.config {
-            Some(s) => config::file::from(s)?,
-            None => {
-                let slot_config = SlotConfig::new(
-                    self.yearly,
-                    self.monthly,
-                    self.daily,
-                    self.hourly,
-                    self.minutely,
-                )?;
-                Config::new(slot_config, &self.pattern, self.regex.as_deref())?
-            }
+        let file_config = match &self.config {
+            Some(s) => file::load(s)?,
+            None => file::ConfigFile::default(),
         };
+        let slots = &file_config.slots;
+
+        let slot_config = SlotConfig::new(
+            self.yearly.unwrap_or(slots.yearly),
+            self.monthly.unwrap_or(slots.monthly),
+            self.weekly.unwrap_or(slots.weekly),
+            self.daily.unwrap_or(slots.daily),
+            self.hourly.unwrap_or(slots.hourly),
+            self.minutely.unwrap_or(slots.minutely),
+        )?;
+
+        let mut pattern = file_config.pattern;
+        pattern.extend(self.pattern.iter().cloned());
+
+        let mut protect = file_config.protect;
+        protect.extend(self.protect.iter().cloned());
+
+        let regex = self.regex.as_deref().or(file_config.regex.as_deref());
+        let use_mtime = self.use_mtime || file_config.use_mtime;
+        let recursive = self.recursive || file_config.recursive;
+
+        let config = Config::new(slot_config, &pattern, regex, use_mtime, &protect)?;
+
+        Plan::new(&config, FilesystemBackend::new(&self.path, recursive, &config)).map_err(Error::new)
+    }
+}
 
-        Plan::new(&config, &self.path).map_err(Error::new)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_plan_overrides_file_slots_and_appends_patterns() {
+        let dir = std::env::temp_dir().join("backedup-test-to-plan-dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("2020-01-01.log"), "a").unwrap();
+        std::fs::write(dir.join("2020-02-01.bak"), "b").unwrap();
+        std::fs::write(dir.join("2020-03-01.txt"), "c").unwrap();
+
+        let config_path = std::env::temp_dir().join("backedup-test-to-plan.toml");
+        std::fs::write(&config_path, "pattern = [\"*.log\"]\n\n[slots]\nyearly = 5\n").unwrap();
+
+        let parser = ArgParser::parse_from(&[
+            "backedup",
+            dir.to_str().unwrap(),
+            "--config",
+            config_path.to_str().unwrap(),
+            // Overrides the file's yearly=5.
+            "--yearly",
+            "1",
+            // Appended to the file's "*.log" pattern.
+            "--pattern",
+            "*.bak",
+        ]);
+
+        let plan = parser.to_plan().unwrap();
+        // Only "*.log"/"*.bak" entries are candidates, and the CLI's yearly=1 (not the file's
+        // 5) keeps a single entry for 2020: the oldest of the two matching entries.
+        assert_eq!(plan.to_keep, vec![dir.join("2020-01-01.log")]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_file(&config_path).unwrap();
     }
 }
@@ -5,10 +5,11 @@ use std::{fmt, io};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::{Display, Formatter};
-use std::fs::{read_dir, remove_file};
+use std::fs::{read_dir, remove_dir_all, remove_file};
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike};
 use log::{error, info};
 use regex::Regex;
 use termion::{color, style};
@@ -35,20 +36,23 @@ impl PartialEq for IoError {
 pub enum BackedUpError {
     #[error("Couldn't open directory {path}")]
     ReadDirError { source: IoError, path: PathBuf },
-    #[error("No write permission for path {0}")]
-    PathPermissionError(PathBuf),
+    #[error("Couldn't remove {path}")]
+    RemoveError { source: IoError, path: PathBuf },
     #[error("At least one slot must be configured")]
     NoSlot,
     #[error("Invalid regex")]
     InvalidRegex(#[from] regex::Error),
     #[error("Regex missing capture group for \"{0}\". -- example: (?P<{0}>\\d{{2}})")]
     MissingCaptureGroup(&'static str),
+    #[error("Failed to remove {failed} of {total} entries")]
+    RemoveIncomplete { failed: usize, total: usize },
 }
 
 #[derive(Copy, Clone)]
 pub struct SlotConfig {
     yearly: usize,
     monthly: usize,
+    weekly: usize,
     daily: usize,
     hourly: usize,
     minutely: usize,
@@ -58,16 +62,18 @@ impl SlotConfig {
     pub fn new(
         years: usize,
         months: usize,
+        weeks: usize,
         days: usize,
         hours: usize,
         minutes: usize,
     ) -> Result<Self, BackedUpError> {
-        if years + months + days + hours + minutes == 0 {
+        if years + months + weeks + days + hours + minutes == 0 {
             return Err(BackedUpError::NoSlot);
         }
         Ok(Self {
             yearly: years,
             monthly: months,
+            weekly: weeks,
             daily: days,
             hourly: hours,
             minutely: minutes,
@@ -79,6 +85,8 @@ pub struct Config {
     slots: SlotConfig,
     pattern: Vec<WildMatch>,
     re: Regex,
+    use_mtime: bool,
+    protect: Vec<WildMatch>,
 }
 
 impl Config {
@@ -86,11 +94,22 @@ impl Config {
     /// An empty [Vec] implies no filter
     ///
     /// An optional regex [String] can be provided for parsing into timeslots.
-    /// At least `year`, `month` and `day` must be provided as named groups
+    /// At least `year`, `month` and `day` must be provided as named groups.
+    ///
+    /// An optional `group` named group partitions entries into independent retention series
+    /// (e.g. `db-*.sql` vs `www-*.tar`), each with its own slots. Entries without a `group`
+    /// capture share a single default group.
+    ///
+    /// When `use_mtime` is set, a filename that doesn't match the regex falls back to the
+    /// file's modification time instead of being dropped.
+    ///
+    /// `protect` wildcard pattern(s) mark entries that are always kept, regardless of slots.
     pub fn new(
         slot_config: SlotConfig,
         pattern: &[String],
         re_str: Option<&str>,
+        use_mtime: bool,
+        protect: &[String],
     ) -> Result<Self, BackedUpError> {
         let pattern = pattern.into_iter().map(|s| WildMatch::new(s)).collect();
         let re = match re_str {
@@ -103,14 +122,20 @@ impl Config {
                 return Err(BackedUpError::MissingCaptureGroup(i));
             }
         }
+        let protect = protect.iter().map(|s| WildMatch::new(s)).collect();
         Ok(Self {
             slots: slot_config,
             pattern,
             re,
+            use_mtime,
+            protect,
         })
     }
 }
 
+/// Group used for entries whose filename has no `group` capture.
+const DEFAULT_GROUP: &str = "default";
+
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash)]
 struct BackupEntry<'a> {
     year: u16,
@@ -118,16 +143,26 @@ struct BackupEntry<'a> {
     day: u8,
     hour: u8,
     minute: u8,
+    group: String,
     path: &'a Path,
 }
 
 impl<'a> BackupEntry<'a> {
-    fn new(path: &'a Path, pattern: &[WildMatch], re: &Regex) -> Option<Self> {
+    fn new(path: &'a Path, pattern: &[WildMatch], re: &Regex, use_mtime: bool) -> Option<Self> {
         let filename = path.file_name()?.to_str()?;
         if !pattern.is_empty() && !pattern.iter().any(|w| w.matches(filename)) {
             return None;
         }
-        let m = re.captures(filename)?;
+        let m = match re.captures(filename) {
+            Some(m) => m,
+            None => {
+                return if use_mtime {
+                    Self::from_mtime(path)
+                } else {
+                    None
+                }
+            }
+        };
         let year = m.name("year")?.as_str().parse().ok()?;
         let month = m.name("month")?.as_str().parse().ok()?;
         let day = m.name("day")?.as_str().parse().ok()?;
@@ -139,23 +174,56 @@ impl<'a> BackupEntry<'a> {
             .name("minute")
             .and_then(|s| s.as_str().parse().ok())
             .unwrap_or(0);
+        let group = m
+            .name("group")
+            .map(|s| s.as_str().to_string())
+            .unwrap_or_else(|| DEFAULT_GROUP.to_string());
         Some(Self {
             year,
             month,
             day,
             hour,
             minute,
+            group,
             path,
         })
     }
 
-    fn get_ordering_tuple(&self) -> (u16, u8, u8, u8, u8) {
-        (self.year, self.month, self.day, self.hour, self.minute)
+    /// Build an entry from the file's modification time, used when the filename itself
+    /// carries no timestamp. Returns `None` (never errors) if the file doesn't exist or its
+    /// mtime can't be read, so synthetic test paths are unaffected.
+    fn from_mtime(path: &'a Path) -> Option<Self> {
+        let modified = path.metadata().ok()?.modified().ok()?;
+        let dt: DateTime<Local> = modified.into();
+        Some(Self {
+            year: dt.year() as u16,
+            month: dt.month() as u8,
+            day: dt.day() as u8,
+            hour: dt.hour() as u8,
+            minute: dt.minute() as u8,
+            group: DEFAULT_GROUP.to_string(),
+            path,
+        })
+    }
+
+    fn get_ordering_tuple(&self) -> (u16, u8, u8, u8, u8, &str, &Path) {
+        (
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.group.as_str(),
+            self.path,
+        )
     }
 }
 
 impl<'a> Ord for BackupEntry<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
+        // `group`/`path` are tie-breakers only, so entries from different groups (or different
+        // files) that happen to share a timestamp never compare `Equal` and collapse in a
+        // `BTreeSet` before grouping gets a chance to separate them.
         self.get_ordering_tuple().cmp(&other.get_ordering_tuple())
     }
 }
@@ -164,9 +232,11 @@ impl<'a> Ord for BackupEntry<'a> {
 pub enum Period {
     Years,
     Months,
+    Weeks,
     Days,
     Hours,
     Minutes,
+    Protected,
 }
 
 impl Period {
@@ -174,9 +244,11 @@ impl Period {
         match self {
             Period::Years => "Years",
             Period::Months => "Months",
+            Period::Weeks => "Weeks",
             Period::Days => "Days",
             Period::Hours => "Hours",
             Period::Minutes => "Minutes",
+            Period::Protected => "Protected",
         }
     }
 }
@@ -196,6 +268,108 @@ lazy_static! {
     .unwrap();
 }
 
+/// A storage target `Plan` can list candidate entries from and prune entries on.
+///
+/// Implement this to prune retention sets on something other than the local filesystem
+/// (e.g. S3, SFTP) without forking the slot-selection logic in [`Plan::from`].
+pub trait Backend {
+    /// List every candidate entry, file or directory.
+    fn list(&self) -> Result<Vec<PathBuf>, BackedUpError>;
+    /// Whether `path`'s parent currently accepts removals.
+    fn is_writable(&self, path: &Path) -> Result<bool, BackedUpError>;
+    /// Remove a single entry previously returned by [`Backend::list`], file or directory.
+    fn remove(&self, path: &Path) -> Result<(), BackedUpError>;
+}
+
+/// [Backend] that lists and removes entries on the local filesystem.
+/// This is the default used by the CLI.
+pub struct FilesystemBackend {
+    path: PathBuf,
+    recursive: bool,
+    pattern: Vec<WildMatch>,
+    re: Regex,
+}
+
+impl FilesystemBackend {
+    /// When `recursive` is set, [`Backend::list`] also walks subdirectories so directory-style
+    /// snapshots (and backup sets nested under subfolders) are discovered too. `config`'s
+    /// pattern/regex are used to recognize a directory that is itself a timestamped entry, so
+    /// walking doesn't descend into it (and `execute` won't later `remove_dir_all` a snapshot
+    /// directory out from under a file kept from inside it).
+    pub fn new<P: AsRef<Path>>(path: P, recursive: bool, config: &Config) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            recursive,
+            pattern: config.pattern.clone(),
+            re: config.re.clone(),
+        }
+    }
+
+    /// Whether `path`'s filename looks like a timestamped entry under this backend's
+    /// pattern/regex, i.e. whether it should be treated as a leaf rather than walked into.
+    fn is_entry(&self, path: &Path) -> bool {
+        let filename = match path.file_name().and_then(|f| f.to_str()) {
+            Some(f) => f,
+            None => return false,
+        };
+        if !self.pattern.is_empty() && !self.pattern.iter().any(|w| w.matches(filename)) {
+            return false;
+        }
+        self.re.is_match(filename)
+    }
+
+    fn walk(&self, dir: &Path) -> Result<Vec<PathBuf>, BackedUpError> {
+        let entries = read_dir(dir).map_err(|e| BackedUpError::ReadDirError {
+            source: IoError(e),
+            path: dir.to_path_buf(),
+        })?;
+        let mut found = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && !self.is_entry(&path) {
+                found.extend(self.walk(&path)?);
+            } else {
+                found.push(path);
+            }
+        }
+        Ok(found)
+    }
+}
+
+impl Backend for FilesystemBackend {
+    fn list(&self) -> Result<Vec<PathBuf>, BackedUpError> {
+        if self.recursive {
+            return self.walk(&self.path);
+        }
+        let dir = read_dir(&self.path).map_err(|e| BackedUpError::ReadDirError {
+            source: IoError(e),
+            path: self.path.clone(),
+        })?;
+        Ok(dir.flatten().map(|x| x.path()).collect())
+    }
+
+    fn is_writable(&self, path: &Path) -> Result<bool, BackedUpError> {
+        let parent = path.parent().unwrap_or(&self.path);
+        let metadata = parent.metadata().map_err(|e| BackedUpError::ReadDirError {
+            source: IoError(e),
+            path: parent.to_path_buf(),
+        })?;
+        Ok(!metadata.permissions().readonly())
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), BackedUpError> {
+        let result = if path.is_dir() {
+            remove_dir_all(path)
+        } else {
+            remove_file(path)
+        };
+        result.map_err(|e| BackedUpError::RemoveError {
+            source: IoError(e),
+            path: path.to_path_buf(),
+        })
+    }
+}
+
 /// Plan for keeping/removing [`PathBuf`] with configured slots.
 ///
 /// [`PathBuf`] that are invalid strings aren't considered for either retention or deletion.
@@ -203,8 +377,9 @@ pub struct Plan {
     pub to_keep: Vec<PathBuf>,
     pub to_remove: Vec<PathBuf>,
     period_map: HashMap<PathBuf, Vec<Period>>,
-    //Original path, used for error reporting if available
-    path: Option<PathBuf>,
+    group_map: HashMap<PathBuf, String>,
+    //Backend used to list/remove entries, used for execution if available
+    backend: Option<Box<dyn Backend>>,
 }
 
 impl Display for Plan {
@@ -231,7 +406,8 @@ impl Display for Plan {
             )?;
             let periods = self.period_map.get(i).unwrap();
             let periods: Vec<_> = periods.iter().map(|x| x.to_string()).collect();
-            writeln!(f, "-> ({})", periods.join(","))?;
+            let group = self.group_map.get(i).unwrap();
+            writeln!(f, "-> ({}) [group: {}]", periods.join(","), group)?;
         }
         writeln!(f, "")?;
         writeln!(
@@ -248,47 +424,107 @@ impl Display for Plan {
 }
 
 impl Plan {
-    pub fn new<P: AsRef<Path>>(config: &Config, path: P) -> Result<Self, BackedUpError> {
-        let dir = read_dir(&path).map_err(|e| BackedUpError::ReadDirError {
-            source: IoError(e),
-            path: path.as_ref().to_path_buf(),
-        })?;
-        let entries: Vec<_> = dir.flatten().map(|x| x.path()).collect();
+    pub fn new<B: Backend + 'static>(config: &Config, backend: B) -> Result<Self, BackedUpError> {
+        let entries = backend.list()?;
         let mut plan = Self::from(&config, &entries);
-        plan.path = Some(path.as_ref().to_path_buf());
+        plan.backend = Some(Box::new(backend));
         Ok(plan)
     }
 
     fn from(config: &Config, entries: &[PathBuf]) -> Self {
         let entries: BTreeSet<_> = entries
             .into_iter()
-            .filter_map(|x| BackupEntry::new(x, &config.pattern, &config.re))
+            .filter_map(|x| BackupEntry::new(x, &config.pattern, &config.re, config.use_mtime))
+            .collect();
+
+        let mut to_keep = BTreeSet::new();
+        let mut period_map: HashMap<PathBuf, Vec<Period>> = HashMap::new();
+        let mut group_map: HashMap<PathBuf, String> = HashMap::new();
+
+        // Protected entries are always kept, regardless of the slots below.
+        for entry in &entries {
+            let filename = entry.path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+            if config.protect.iter().any(|w| w.matches(filename)) {
+                to_keep.insert(entry.clone());
+                period_map
+                    .entry(entry.path.to_path_buf())
+                    .or_default()
+                    .push(Period::Protected);
+            }
+        }
+
+        let mut by_group: BTreeMap<String, Vec<&BackupEntry>> = BTreeMap::new();
+        for entry in &entries {
+            by_group.entry(entry.group.clone()).or_default().push(entry);
+        }
+
+        for (group, group_entries) in by_group {
+            for entry in &group_entries {
+                group_map.insert(entry.path.to_path_buf(), group.clone());
+            }
+            Self::select_keepers(&group_entries, config.slots, &mut to_keep, &mut period_map);
+        }
+
+        let to_remove: Vec<_> = entries
+            .difference(&to_keep)
+            .map(|x| x.path.to_path_buf())
             .collect();
+        let to_keep: Vec<_> = to_keep.into_iter().map(|x| x.path.to_path_buf()).collect();
+        assert_eq!(entries.len(), &to_keep.len() + &to_remove.len());
+        Self {
+            to_keep,
+            to_remove,
+            period_map,
+            group_map,
+            backend: None,
+        }
+    }
+
+    /// Fill the yearly/monthly/weekly/daily/hourly/minutely slots for a single group's entries,
+    /// inserting the chosen [`BackupEntry`] into `to_keep` and recording its [`Period`]s.
+    fn select_keepers<'a>(
+        entries: &[&'a BackupEntry<'a>],
+        slots: SlotConfig,
+        to_keep: &mut BTreeSet<BackupEntry<'a>>,
+        period_map: &mut HashMap<PathBuf, Vec<Period>>,
+    ) {
         let mut year_slots = BTreeMap::new();
         let mut month_slots = BTreeMap::new();
+        let mut week_slots: BTreeMap<(i32, u8), &BackupEntry> = BTreeMap::new();
         let mut day_slots = BTreeMap::new();
         let mut hour_slots = BTreeMap::new();
         let mut minute_slots = BTreeMap::new();
+        // entries is ascending (oldest first); insert in reverse so that when two entries
+        // collide on the same bucket key (e.g. the same year), the later insert - the oldest
+        // entry - is the one that represents that bucket.
         for entry in entries.iter().rev() {
-            year_slots.insert(entry.year, entry);
-            month_slots.insert((entry.year, entry.month), entry);
-            day_slots.insert((entry.year, entry.month, entry.day), entry);
-            hour_slots.insert((entry.year, entry.month, entry.day, entry.hour), entry);
+            year_slots.insert(entry.year, *entry);
+            month_slots.insert((entry.year, entry.month), *entry);
+            // The regex only validates digit counts, not calendar validity, so a matching
+            // filename can carry an out-of-range month/day; from_ymd_opt lets such an entry
+            // just skip the weekly bucket instead of panicking.
+            if let Some(iso_week) =
+                NaiveDate::from_ymd_opt(entry.year as i32, entry.month as u32, entry.day as u32)
+                    .map(|d| d.iso_week())
+            {
+                week_slots.insert((iso_week.year(), iso_week.week() as u8), *entry);
+            }
+            day_slots.insert((entry.year, entry.month, entry.day), *entry);
+            hour_slots.insert((entry.year, entry.month, entry.day, entry.hour), *entry);
             minute_slots.insert(
                 (entry.year, entry.month, entry.day, entry.hour, entry.minute),
-                entry,
+                *entry,
             );
         }
 
-        let mut to_keep = BTreeSet::new();
-        let mut period_map: HashMap<PathBuf, Vec<Period>> = HashMap::new();
         let SlotConfig {
             yearly,
             monthly,
+            weekly,
             daily,
             hourly,
             minutely,
-        } = config.slots;
+        } = slots;
         for (_, entry) in year_slots.into_iter().rev().take(yearly) {
             to_keep.insert(entry.clone());
             period_map
@@ -303,6 +539,13 @@ impl Plan {
                 .or_default()
                 .push(Period::Months);
         }
+        for (_, entry) in week_slots.into_iter().rev().take(weekly) {
+            to_keep.insert(entry.clone());
+            period_map
+                .entry(entry.path.to_path_buf())
+                .or_default()
+                .push(Period::Weeks);
+        }
         for (_, entry) in day_slots.into_iter().rev().take(daily) {
             to_keep.insert(entry.clone());
             period_map
@@ -324,55 +567,49 @@ impl Plan {
                 .or_default()
                 .push(Period::Minutes);
         }
-
-        let to_remove: Vec<_> = entries
-            .difference(&to_keep)
-            .map(|x| x.path.to_path_buf())
-            .collect();
-        let to_keep: Vec<_> = to_keep.into_iter().map(|x| x.path.to_path_buf()).collect();
-        assert_eq!(entries.len(), &to_keep.len() + &to_remove.len());
-        Self {
-            to_keep,
-            to_remove,
-            period_map,
-            path: None,
-        }
     }
 
-    /// Execute plan and remove timestamped files not matching any slots
+    /// Execute plan and remove timestamped entries not matching any slots
     pub fn execute(&self) -> Result<(), BackedUpError> {
-        //Check if path has write permission
-        if let Some(path) = &self.path {
-            if path
-                .metadata()
-                .map_err(|e| BackedUpError::ReadDirError {
-                    source: IoError(e),
-                    path: path.to_path_buf(),
-                })?
-                .permissions()
-                .readonly()
-            {
-                error!("No write permission for path {}", path.to_str().unwrap());
-                return Err(BackedUpError::PathPermissionError(path.to_path_buf()));
-            }
-        }
+        let backend = match &self.backend {
+            Some(backend) => backend,
+            None => return Ok(()),
+        };
 
         if self.to_remove.is_empty() {
             info!("No file to remove")
         }
 
-        //Remove files
+        //Remove entries, checking write permission on each entry's parent
+        let mut failed = 0;
         for p in self.to_remove.iter() {
             let filename = p.to_str().unwrap();
-            match remove_file(p) {
-                Ok(_) => {
-                    info!("removed file {}", filename)
+            match backend.is_writable(p) {
+                Ok(true) => match backend.remove(p) {
+                    Ok(_) => {
+                        info!("removed file {}", filename)
+                    }
+                    Err(e) => {
+                        error!("failed to remove file \"{}\": {}", filename, e);
+                        failed += 1;
+                    }
+                },
+                Ok(false) => {
+                    error!("No write permission for \"{}\"", filename);
+                    failed += 1;
                 }
                 Err(e) => {
-                    error!("failed to remove file \"{}\": {}", filename, e)
+                    error!("failed to check write permission for \"{}\": {}", filename, e);
+                    failed += 1;
                 }
             }
         }
+        if failed > 0 {
+            return Err(BackedUpError::RemoveIncomplete {
+                failed,
+                total: self.to_remove.len(),
+            });
+        }
         Ok(())
     }
 }
@@ -414,8 +651,8 @@ mod tests {
             30,
             ".log",
         ));
-        let slot_config = SlotConfig::new(3, 0, 0, 0, 0).unwrap();
-        let mut config = Config::new(slot_config, &vec![], None).unwrap();
+        let slot_config = SlotConfig::new(3, 0, 0, 0, 0, 0).unwrap();
+        let mut config = Config::new(slot_config, &vec![], None, false, &vec![]).unwrap();
 
         let plan = Plan::from(&config, &parsed_backups);
         assert_eq!(plan.to_keep.len(), 3);
@@ -437,39 +674,124 @@ mod tests {
     fn test_custom_regex() {
         let fmt = "%y%m%d";
         let parsed_backups = create_test_data(fmt, Utc.ymd(2015, 1, 1).and_hms(0, 0, 0), 400, "");
-        let slot_config = SlotConfig::new(3, 13, 30, 0, 0).unwrap();
+        let slot_config = SlotConfig::new(3, 13, 0, 30, 0, 0).unwrap();
         let re_str = r"(?P<year>\d{2})(?P<month>\d{2})(?P<day>\d{2})";
-        let config = Config::new(slot_config, &vec![], Some(re_str)).unwrap();
+        let config = Config::new(slot_config, &vec![], Some(re_str), false, &vec![]).unwrap();
         let plan = Plan::from(&config, &parsed_backups);
         assert_eq!(plan.to_keep.len(), 43);
     }
 
+    #[test]
+    fn test_group_isolation() {
+        let fmt = "%Y-%m-%d";
+        let mut parsed_backups = Vec::new();
+        for path in create_test_data(fmt, Utc.ymd(2015, 1, 1).and_hms(0, 0, 0), 5, "") {
+            parsed_backups.push(PathBuf::from(format!("db-{}", path.to_str().unwrap())));
+        }
+        // Same timestamps as the "db" series, so entries only differ by group/path.
+        for path in create_test_data(fmt, Utc.ymd(2015, 1, 1).and_hms(0, 0, 0), 5, "") {
+            parsed_backups.push(PathBuf::from(format!("www-{}", path.to_str().unwrap())));
+        }
+
+        let slot_config = SlotConfig::new(0, 0, 0, 2, 0, 0).unwrap();
+        let re_str = r"(?P<group>db|www)-(?P<year>\d{4})-(?P<month>\d{2})-(?P<day>\d{2})";
+        let config = Config::new(slot_config, &vec![], Some(re_str), false, &vec![]).unwrap();
+
+        let plan = Plan::from(&config, &parsed_backups);
+        // 2 daily slots per group, not one slot set shared/starved across both groups.
+        assert_eq!(plan.to_keep.len(), 4);
+        assert_eq!(plan.to_remove.len(), 6);
+        assert!(plan
+            .to_keep
+            .iter()
+            .any(|p| p.to_str().unwrap().starts_with("db-")));
+        assert!(plan
+            .to_keep
+            .iter()
+            .any(|p| p.to_str().unwrap().starts_with("www-")));
+    }
+
+    #[test]
+    fn test_weekly_slot() {
+        let fmt = "%Y-%m-%d";
+        // Spans the ISO year boundary: 2014-12-29 is in ISO week 1 of 2015.
+        let parsed_backups =
+            create_test_data(fmt, Utc.ymd(2015, 2, 1).and_hms(0, 0, 0), 60, "");
+        let slot_config = SlotConfig::new(0, 0, 5, 0, 0, 0).unwrap();
+        let config = Config::new(slot_config, &vec![], None, false, &vec![]).unwrap();
+        let plan = Plan::from(&config, &parsed_backups);
+        assert_eq!(plan.to_keep.len(), 5);
+    }
+
+    #[test]
+    fn test_invalid_calendar_date_does_not_panic() {
+        // Matches the default regex (digit counts only) but isn't a real calendar date.
+        let mut parsed_backups = vec![PathBuf::from("2021-13-40.tar")];
+        parsed_backups.extend(create_test_data(
+            "%Y-%m-%d",
+            Utc.ymd(2015, 1, 1).and_hms(0, 0, 0),
+            5,
+            "",
+        ));
+        let slot_config = SlotConfig::new(0, 0, 1, 0, 0, 0).unwrap();
+        let config = Config::new(slot_config, &vec![], None, false, &vec![]).unwrap();
+        let plan = Plan::from(&config, &parsed_backups);
+        // The malformed entry still parses (year/month/day capture), it just can't contribute
+        // to the weekly bucket, so it's removed like any other entry outside the single slot.
+        assert!(plan.to_remove.contains(&PathBuf::from("2021-13-40.tar")));
+    }
+
+    #[test]
+    fn test_protected_entry() {
+        let fmt = "%Y-%m-%d";
+        let mut parsed_backups =
+            create_test_data(fmt, Utc.ymd(2015, 1, 1).and_hms(0, 0, 0), 10, "");
+        parsed_backups.push(PathBuf::from("2010-06-15-release.tar"));
+
+        let slot_config = SlotConfig::new(1, 0, 0, 0, 0, 0).unwrap();
+        let config = Config::new(
+            slot_config,
+            &vec![],
+            None,
+            false,
+            &vec!["*-release.tar".to_string()],
+        )
+        .unwrap();
+
+        let plan = Plan::from(&config, &parsed_backups);
+        // 1 yearly slot + the protected entry, which is far outside any slot.
+        assert_eq!(plan.to_keep.len(), 2);
+        assert!(plan
+            .to_keep
+            .contains(&PathBuf::from("2010-06-15-release.tar")));
+    }
+
     #[test]
     fn test_no_slot() {
-        let slot_config = SlotConfig::new(0, 0, 0, 0, 0);
+        let slot_config = SlotConfig::new(0, 0, 0, 0, 0, 0);
         assert_eq!(BackedUpError::NoSlot, slot_config.err().unwrap());
     }
 
     #[test]
     fn test_missing_named_group() {
-        let slot_config = SlotConfig::new(1, 0, 0, 0, 0).unwrap();
+        let slot_config = SlotConfig::new(1, 0, 0, 0, 0, 0).unwrap();
         let re_str = r"(?P<month>\d{2})(?P<day>\d{2})";
 
-        let config = Config::new(slot_config, &vec![], Some(re_str));
+        let config = Config::new(slot_config, &vec![], Some(re_str), false, &vec![]);
         assert_eq!(
             BackedUpError::MissingCaptureGroup("year"),
             config.err().unwrap()
         );
 
         let re_str = r"(?P<year>\d{2})(?P<day>\d{2})";
-        let config = Config::new(slot_config, &vec![], Some(re_str));
+        let config = Config::new(slot_config, &vec![], Some(re_str), false, &vec![]);
         assert_eq!(
             BackedUpError::MissingCaptureGroup("month"),
             config.err().unwrap()
         );
 
         let re_str = r"(?P<year>\d{2})(?P<month>\d{2})";
-        let config = Config::new(slot_config, &vec![], Some(re_str));
+        let config = Config::new(slot_config, &vec![], Some(re_str), false, &vec![]);
         assert_eq!(
             BackedUpError::MissingCaptureGroup("day"),
             config.err().unwrap()
@@ -479,8 +801,8 @@ mod tests {
     #[test]
     fn test_invalid_regex() {
         let re_str = r"/(notaregex";
-        let slot_config = SlotConfig::new(1, 0, 0, 0, 0).unwrap();
-        let config = Config::new(slot_config, &vec![], Some(re_str));
+        let slot_config = SlotConfig::new(1, 0, 0, 0, 0, 0).unwrap();
+        let config = Config::new(slot_config, &vec![], Some(re_str), false, &vec![]);
         assert!(matches!(
             config.err().unwrap(),
             BackedUpError::InvalidRegex(_)
@@ -493,7 +815,69 @@ mod tests {
         use std::os::unix::ffi::OsStringExt;
         let invalid_utf = b"2021-04-11\xe7";
         let path = PathBuf::from(OsString::from_vec(invalid_utf.to_vec()));
-        let entry = BackupEntry::new(&path, &vec![], &RE);
+        let entry = BackupEntry::new(&path, &vec![], &RE, false);
         assert_eq!(entry, None);
     }
+
+    #[test]
+    fn test_filesystem_backend_list_and_remove() {
+        let dir = std::env::temp_dir().join("backedup-test-backend");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+        let file_a = dir.join("2020-01-01");
+        let file_b = dir.join("2020-01-02");
+        std::fs::write(&file_a, "a").unwrap();
+        std::fs::write(&file_b, "b").unwrap();
+
+        let slot_config = SlotConfig::new(1, 0, 0, 0, 0, 0).unwrap();
+        let config = Config::new(slot_config, &vec![], None, false, &vec![]).unwrap();
+        let backend = FilesystemBackend::new(&dir, false, &config);
+
+        let mut listed = backend.list().unwrap();
+        listed.sort();
+        assert_eq!(listed, vec![file_a.clone(), file_b.clone()]);
+
+        assert!(backend.is_writable(&file_a).unwrap());
+
+        backend.remove(&file_a).unwrap();
+        assert!(!file_a.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_filesystem_backend_recursive_leaf() {
+        let dir = std::env::temp_dir().join("backedup-test-backend-recursive");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+        // A directory-style snapshot matching the timestamp regex...
+        let snapshot_dir = dir.join("2020-01-01");
+        std::fs::create_dir(&snapshot_dir).unwrap();
+        // ...with a nested, independently timestamped file inside it.
+        std::fs::write(snapshot_dir.join("2020-01-01-inner.log"), "data").unwrap();
+
+        let slot_config = SlotConfig::new(1, 0, 0, 0, 0, 0).unwrap();
+        let config = Config::new(slot_config, &vec![], None, false, &vec![]).unwrap();
+        let backend = FilesystemBackend::new(&dir, true, &config);
+
+        // The snapshot directory matches the regex, so it's a leaf: listed itself, not
+        // walked into.
+        assert_eq!(backend.list().unwrap(), vec![snapshot_dir.clone()]);
+
+        backend.remove(&snapshot_dir).unwrap();
+        assert!(!snapshot_dir.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_use_mtime_fallback() {
+        let path = std::env::temp_dir().join("backedup-test-no-timestamp.bak");
+        std::fs::write(&path, "data").unwrap();
+
+        assert_eq!(BackupEntry::new(&path, &vec![], &RE, false), None);
+        assert!(BackupEntry::new(&path, &vec![], &RE, true).is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }